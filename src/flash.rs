@@ -0,0 +1,65 @@
+//! A one-shot flash message layered over [`Cookies`], inspired by Rocket's
+//! `response::flash`.
+//!
+//! Flash messages are a common building block of post/redirect/get flows:
+//! set one before redirecting away from a form handler, then take it when
+//! rendering the page the user lands on. The message is removed the moment
+//! it's read, so it's displayed exactly once.
+
+use crate::Cookies;
+use cookie::Cookie;
+
+const FLASH_COOKIE_NAME: &str = "_flash";
+
+/// A one-shot flash message stored in a short-lived cookie.
+///
+/// `Flash` has no state of its own; it's a pair of free functions over
+/// [`Cookies`] that agree on a cookie name and value encoding.
+pub struct Flash;
+
+impl Flash {
+    /// Stores `message` of the given `kind` (e.g. `"error"`, `"success"`)
+    /// in a `Path=/` cookie, to be read back with [`Flash::take`] on the
+    /// next request.
+    pub fn set(cookies: &mut Cookies, kind: &str, message: &str) {
+        let mut cookie = Cookie::new(FLASH_COOKIE_NAME, format!("{kind}:{message}"));
+        cookie.set_path("/");
+        cookies.add(cookie);
+    }
+
+    /// Returns the current flash message, if any, as `(kind, message)`, and
+    /// removes it so it won't be returned again.
+    pub fn take(cookies: &mut Cookies) -> Option<(String, String)> {
+        let value = cookies.get(FLASH_COOKIE_NAME)?.value().to_owned();
+
+        let mut removal = Cookie::new(FLASH_COOKIE_NAME, "");
+        removal.set_path("/");
+        cookies.remove(removal);
+
+        let (kind, message) = value.split_once(':')?;
+        Some((kind.to_owned(), message.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_then_take_returns_the_message_once() {
+        let mut cookies = Cookies::new(None, None, false);
+        Flash::set(&mut cookies, "error", "invalid credentials");
+
+        assert_eq!(
+            Flash::take(&mut cookies),
+            Some(("error".to_owned(), "invalid credentials".to_owned()))
+        );
+        assert_eq!(Flash::take(&mut cookies), None);
+    }
+
+    #[test]
+    fn take_without_a_flash_cookie_is_none() {
+        let mut cookies = Cookies::new(None, None, false);
+        assert_eq!(Flash::take(&mut cookies), None);
+    }
+}
@@ -1,4 +1,6 @@
-use cookie::{Cookie, CookieJar};
+pub mod flash;
+
+use cookie::{Cookie, CookieJar, Key};
 use futures_util::ready;
 use http::{header, HeaderValue, Request, Response};
 use parking_lot::Mutex;
@@ -19,17 +21,26 @@ pub struct Cookies {
     header: Option<HeaderValue>,
     jar: Option<CookieJar>,
     changed: bool,
+    defaults: Option<Cookie<'static>>,
+    percent_encode: bool,
 }
 
 impl Cookies {
-    fn new(header: Option<HeaderValue>) -> Self {
+    pub(crate) fn new(
+        header: Option<HeaderValue>,
+        defaults: Option<Cookie<'static>>,
+        percent_encode: bool,
+    ) -> Self {
         Self {
             header,
+            defaults,
+            percent_encode,
             ..Default::default()
         }
     }
 
-    pub fn add(&mut self, cookie: Cookie<'static>) {
+    pub fn add(&mut self, mut cookie: Cookie<'static>) {
+        self.apply_defaults(&mut cookie);
         self.changed = true;
         self.jar().add(cookie);
     }
@@ -39,15 +50,74 @@ impl Cookies {
         self.jar().get(name)
     }
 
-    pub fn remove(&mut self, cookie: Cookie<'static>) {
+    pub fn remove(&mut self, mut cookie: Cookie<'static>) {
+        self.apply_defaults(&mut cookie);
         self.changed = true;
         self.jar().remove(cookie);
     }
 
+    /// Fills in any attribute `cookie` left unset from the layer's
+    /// configured defaults, so an explicit attribute on `cookie` always
+    /// wins.
+    fn apply_defaults(&self, cookie: &mut Cookie<'static>) {
+        let defaults = match &self.defaults {
+            Some(defaults) => defaults,
+            None => return,
+        };
+        if cookie.path().is_none() {
+            if let Some(path) = defaults.path() {
+                cookie.set_path(path.to_owned());
+            }
+        }
+        if cookie.domain().is_none() {
+            if let Some(domain) = defaults.domain() {
+                cookie.set_domain(domain.to_owned());
+            }
+        }
+        if cookie.same_site().is_none() {
+            if let Some(same_site) = defaults.same_site() {
+                cookie.set_same_site(same_site);
+            }
+        }
+        if cookie.secure().is_none() {
+            if let Some(secure) = defaults.secure() {
+                cookie.set_secure(secure);
+            }
+        }
+        if cookie.http_only().is_none() {
+            if let Some(http_only) = defaults.http_only() {
+                cookie.set_http_only(http_only);
+            }
+        }
+    }
+
     pub fn iter(&mut self) -> cookie::Iter<'_> {
         self.jar().iter()
     }
 
+    /// Returns a wrapper around this jar that signs cookies with `keys`'
+    /// primary key on the way in. On the way out, verification is attempted
+    /// against the primary key and then, in order, each of `keys`' previous
+    /// keys, so cookies signed before a key rotation keep verifying.
+    pub fn signed<'a>(&'a mut self, keys: &'a Keys) -> SignedCookies<'a> {
+        SignedCookies {
+            cookies: self,
+            keys,
+        }
+    }
+
+    /// Returns a wrapper around this jar that encrypts and authenticates
+    /// cookies with `keys`' primary key on the way in. On the way out,
+    /// decryption is attempted against the primary key and then, in order,
+    /// each of `keys`' previous keys, so cookies encrypted before a key
+    /// rotation keep decrypting.
+    pub fn private<'a>(&'a mut self, keys: &'a Keys) -> PrivateCookies<'a> {
+        PrivateCookies {
+            cookies: self,
+            keys,
+        }
+    }
+
     /// Cached jar
     fn jar(&mut self) -> &mut CookieJar {
         if self.jar.is_none() {
@@ -63,6 +133,170 @@ impl Cookies {
     }
 }
 
+/// A view of a [`Cookies`] jar whose cookies are authenticated with an
+/// HMAC-SHA256 signature, obtained via [`Cookies::signed`].
+///
+/// Signed cookies aren't confidential: their value is readable by the
+/// client, only tamper-proof. Use [`Cookies::private`] if the value must
+/// also stay hidden from the client.
+pub struct SignedCookies<'a> {
+    cookies: &'a mut Cookies,
+    keys: &'a Keys,
+}
+
+impl<'a> SignedCookies<'a> {
+    /// Signs `cookie` with the primary key and adds it to the jar.
+    pub fn add(&mut self, cookie: Cookie<'static>) {
+        self.cookies.changed = true;
+        self.cookies
+            .jar()
+            .signed_mut(self.keys.primary())
+            .add(cookie);
+    }
+
+    /// Verifies and returns the cookie named `name`, or `None` if it's
+    /// missing or fails to verify against the primary key and every
+    /// previous key. A successful verification against a previous key
+    /// re-signs the cookie with the primary key.
+    pub fn get(&mut self, name: &str) -> Option<Cookie<'static>> {
+        if let Some(cookie) = self.cookies.jar().signed_mut(self.keys.primary()).get(name) {
+            return Some(cookie);
+        }
+
+        for key in self.keys.previous() {
+            if let Some(cookie) = self.cookies.jar().signed_mut(key).get(name) {
+                self.cookies.changed = true;
+                self.cookies
+                    .jar()
+                    .signed_mut(self.keys.primary())
+                    .add(cookie.clone());
+                return Some(cookie);
+            }
+        }
+
+        None
+    }
+
+    /// Removes `cookie` from the jar.
+    pub fn remove(&mut self, cookie: Cookie<'static>) {
+        self.cookies.changed = true;
+        self.cookies
+            .jar()
+            .signed_mut(self.keys.primary())
+            .remove(cookie);
+    }
+}
+
+/// A view of a [`Cookies`] jar whose cookies are encrypted and
+/// authenticated (AEAD) with the primary key of a [`Keys`] set, obtained via
+/// [`Cookies::private`].
+///
+/// Unlike [`SignedCookies`], the cookie's value is never readable by the
+/// client, making this suitable for session identifiers or other data that
+/// must stay confidential.
+pub struct PrivateCookies<'a> {
+    cookies: &'a mut Cookies,
+    keys: &'a Keys,
+}
+
+impl<'a> PrivateCookies<'a> {
+    /// Encrypts `cookie` with the primary key and adds it to the jar.
+    pub fn add(&mut self, cookie: Cookie<'static>) {
+        self.cookies.changed = true;
+        self.cookies
+            .jar()
+            .private_mut(self.keys.primary())
+            .add(cookie);
+    }
+
+    /// Decrypts and returns the cookie named `name`, or `None` if it's
+    /// missing or fails to decrypt with the primary key and every previous
+    /// key. A successful decryption with a previous key re-encrypts the
+    /// cookie with the primary key.
+    pub fn get(&mut self, name: &str) -> Option<Cookie<'static>> {
+        if let Some(cookie) = self
+            .cookies
+            .jar()
+            .private_mut(self.keys.primary())
+            .get(name)
+        {
+            return Some(cookie);
+        }
+
+        for key in self.keys.previous() {
+            if let Some(cookie) = self.cookies.jar().private_mut(key).get(name) {
+                self.cookies.changed = true;
+                self.cookies
+                    .jar()
+                    .private_mut(self.keys.primary())
+                    .add(cookie.clone());
+                return Some(cookie);
+            }
+        }
+
+        None
+    }
+
+    /// Removes `cookie` from the jar.
+    pub fn remove(&mut self, cookie: Cookie<'static>) {
+        self.cookies.changed = true;
+        self.cookies
+            .jar()
+            .private_mut(self.keys.primary())
+            .remove(cookie);
+    }
+}
+
+/// A primary [`Key`] plus an ordered list of previously-used keys.
+///
+/// When verifying or decrypting signed/private cookies, the primary key is
+/// tried first, then each previous key in order. This allows an operator to
+/// rotate to a new primary key — by moving the old one into the previous
+/// list — without invalidating cookies that were issued before the
+/// rotation.
+#[derive(Clone)]
+pub struct Keys {
+    primary: Key,
+    previous: Vec<Key>,
+}
+
+impl Keys {
+    /// Creates a key set with `primary` as its only key.
+    pub fn new(primary: Key) -> Self {
+        Self {
+            primary,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Sets the ordered list of keys to fall back to when `primary` fails to
+    /// verify or decrypt a cookie.
+    pub fn with_previous(mut self, previous: Vec<Key>) -> Self {
+        self.previous = previous;
+        self
+    }
+
+    pub fn primary(&self) -> &Key {
+        &self.primary
+    }
+
+    pub fn previous(&self) -> &[Key] {
+        &self.previous
+    }
+}
+
+impl std::fmt::Debug for Keys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keys").finish_non_exhaustive()
+    }
+}
+
+impl From<Key> for Keys {
+    fn from(primary: Key) -> Self {
+        Self::new(primary)
+    }
+}
+
 fn jar_from_str(s: &str) -> CookieJar {
     let mut jar = CookieJar::new();
     for cookie_str in s.split(';').map(str::trim) {
@@ -74,17 +308,25 @@ fn jar_from_str(s: &str) -> CookieJar {
 }
 
 #[derive(Clone, Debug)]
-pub struct CookieService<S> {
+pub struct CookieManager<S> {
     inner: S,
+    keys: Option<Keys>,
+    defaults: Option<Cookie<'static>>,
+    percent_encode: bool,
 }
 
-impl<S> CookieService<S> {
+impl<S> CookieManager<S> {
     pub fn new(inner: S) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            keys: None,
+            defaults: None,
+            percent_encode: false,
+        }
     }
 }
 
-impl<ReqBody, ResBody, S> Service<Request<ReqBody>> for CookieService<S>
+impl<ReqBody, ResBody, S> Service<Request<ReqBody>> for CookieManager<S>
 where
     S: Service<Request<ReqBody>, Response = Response<ResBody>>,
 {
@@ -99,8 +341,15 @@ where
 
     fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
         let value = req.headers().get(header::COOKIE).cloned();
-        let cookies = Arc::new(Mutex::new(Cookies::new(value)));
+        let cookies = Arc::new(Mutex::new(Cookies::new(
+            value,
+            self.defaults.clone(),
+            self.percent_encode,
+        )));
         req.extensions_mut().insert(cookies.clone());
+        if let Some(keys) = &self.keys {
+            req.extensions_mut().insert(keys.clone());
+        }
 
         ResponseFuture {
             future: self.inner.call(req),
@@ -109,7 +358,7 @@ where
     }
 }
 
-/// Response future for [`CookieService`].
+/// Response future for [`CookieManager`].
 #[pin_project]
 #[derive(Debug)]
 pub struct ResponseFuture<F> {
@@ -130,10 +379,18 @@ where
 
         let mut cookies = this.cookies.lock();
         if cookies.changed {
+            let percent_encode = cookies.percent_encode;
             let values: Vec<_> = cookies
                 .jar()
                 .delta()
-                .filter_map(|c| HeaderValue::from_str(&c.to_string()).ok())
+                .filter_map(|c| {
+                    let value = if percent_encode {
+                        c.encoded().to_string()
+                    } else {
+                        c.to_string()
+                    };
+                    HeaderValue::from_str(&value).ok()
+                })
                 .collect();
             let headers = res.headers_mut();
             for value in values {
@@ -145,14 +402,88 @@ where
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct CookieLayer;
+#[derive(Clone, Debug, Default)]
+pub struct CookieManagerLayer {
+    keys: Option<Keys>,
+    defaults: Option<Cookie<'static>>,
+    percent_encode: bool,
+}
+
+impl CookieManagerLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configures the primary [`Key`] used to sign/encrypt cookies added
+    /// through [`Cookies::signed`]/[`Cookies::private`]. The key, wrapped in
+    /// a [`Keys`] set, is cloned into the request extensions so handlers can
+    /// retrieve it (as `Keys`) alongside the cookie jar.
+    pub fn with_key(mut self, key: Key) -> Self {
+        self.keys = Some(Keys::new(key));
+        self
+    }
 
-impl<S> Layer<S> for CookieLayer {
-    type Service = CookieService<S>;
+    /// Sets the keys to fall back to, in order, when the primary key set via
+    /// [`with_key`](Self::with_key) fails to verify or decrypt a cookie,
+    /// replacing any set by an earlier call.
+    /// This allows signing/encryption keys to be rotated without
+    /// invalidating cookies issued under the previous key. Must be called
+    /// after [`with_key`](Self::with_key).
+    pub fn with_previous_keys(mut self, previous: Vec<Key>) -> Self {
+        self.keys = self.keys.map(|keys| keys.with_previous(previous));
+        self
+    }
+
+    /// Configures default attributes (path, domain, `SameSite`, `Secure`,
+    /// `HttpOnly`, ...) applied to every cookie added through a [`Cookies`]
+    /// built from this layer. Only the attributes `defaults` has set are
+    /// used, and only when the cookie passed to [`Cookies::add`] doesn't
+    /// already set them itself. [`Cookies::remove`] fills them in the same
+    /// way. Cookies added or removed through [`Cookies::signed`] or
+    /// [`Cookies::private`] are not given these defaults.
+    ///
+    /// For example, to mirror Rocket's defaults of `Path=/` and
+    /// `SameSite=Strict`:
+    ///
+    /// ```
+    /// use cookie::{Cookie, SameSite};
+    /// use tower_cookies::CookieManagerLayer;
+    ///
+    /// let mut defaults = Cookie::new("", "");
+    /// defaults.set_path("/");
+    /// defaults.set_same_site(SameSite::Strict);
+    ///
+    /// let layer = CookieManagerLayer::new().with_defaults(defaults);
+    /// ```
+    pub fn with_defaults(mut self, defaults: Cookie<'static>) -> Self {
+        self.defaults = Some(defaults);
+        self
+    }
+
+    /// When enabled, cookie values are percent-encoded in the `Set-Cookie`
+    /// header via [`cookie::Cookie::encoded`], matching the `Cookie::parse_encoded`
+    /// used to read the incoming `Cookie` header. This lets applications
+    /// store arbitrary UTF-8 or delimiter-containing values (`;`, `=`,
+    /// spaces, ...) without manually escaping them; commas are left as is.
+    /// Defaults to
+    /// `false` for compatibility with clients that don't expect encoded
+    /// cookie values.
+    pub fn percent_encode(mut self, percent_encode: bool) -> Self {
+        self.percent_encode = percent_encode;
+        self
+    }
+}
+
+impl<S> Layer<S> for CookieManagerLayer {
+    type Service = CookieManager<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        CookieService { inner }
+        CookieManager {
+            inner,
+            keys: self.keys.clone(),
+            defaults: self.defaults.clone(),
+            percent_encode: self.percent_encode,
+        }
     }
 }
 
@@ -195,7 +526,7 @@ mod tests {
                     cookies.lock().remove(Cookie::new("foo", ""));
                 }),
             )
-            .layer(CookieLayer)
+            .layer(CookieManagerLayer::new())
             .boxed()
     }
 
@@ -241,4 +572,150 @@ mod tests {
         assert!(hdr.starts_with("foo=; Max-Age=0; Expires=Tue"));
         assert_eq!(hdrs.next(), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn signed_cookies_round_trip() {
+        let keys = Keys::new(Key::generate());
+        let mut cookies = Cookies::new(None, None, false);
+        cookies.signed(&keys).add(Cookie::new("user_id", "42"));
+
+        let value = cookies
+            .signed(&keys)
+            .get("user_id")
+            .map(|c| c.value().to_owned());
+        assert_eq!(value, Some("42".to_owned()));
+    }
+
+    #[test]
+    fn signed_cookies_reject_tampering() {
+        let keys = Keys::new(Key::generate());
+        let other_keys = Keys::new(Key::generate());
+        let mut cookies = Cookies::new(None, None, false);
+        cookies.signed(&keys).add(Cookie::new("user_id", "42"));
+
+        assert_eq!(cookies.signed(&other_keys).get("user_id"), None);
+    }
+
+    #[test]
+    fn signed_cookies_verify_against_previous_key_and_rotate() {
+        let old_key = Key::generate();
+        let new_keys = Keys::new(Key::generate()).with_previous(vec![old_key.clone()]);
+
+        let mut cookies = Cookies::new(None, None, false);
+        cookies
+            .signed(&Keys::new(old_key))
+            .add(Cookie::new("user_id", "42"));
+
+        let value = cookies
+            .signed(&new_keys)
+            .get("user_id")
+            .map(|c| c.value().to_owned());
+        assert_eq!(value, Some("42".to_owned()));
+
+        // The cookie should now verify against the new primary key alone.
+        let value = cookies
+            .signed(&Keys::new(new_keys.primary().clone()))
+            .get("user_id")
+            .map(|c| c.value().to_owned());
+        assert_eq!(value, Some("42".to_owned()));
+    }
+
+    #[test]
+    fn private_cookies_round_trip() {
+        let keys = Keys::new(Key::generate());
+        let mut cookies = Cookies::new(None, None, false);
+        cookies.private(&keys).add(Cookie::new("session", "secret"));
+
+        let value = cookies
+            .private(&keys)
+            .get("session")
+            .map(|c| c.value().to_owned());
+        assert_eq!(value, Some("secret".to_owned()));
+    }
+
+    #[test]
+    fn private_cookies_hide_value_on_the_wire() {
+        let keys = Keys::new(Key::generate());
+        let mut cookies = Cookies::new(None, None, false);
+        cookies.private(&keys).add(Cookie::new("session", "secret"));
+
+        let raw = cookies.get("session").map(|c| c.value().to_owned());
+        assert_ne!(raw, Some("secret".to_owned()));
+    }
+
+    #[test]
+    fn private_cookies_decrypt_against_previous_key_and_rotate() {
+        let old_key = Key::generate();
+        let new_keys = Keys::new(Key::generate()).with_previous(vec![old_key.clone()]);
+
+        let mut cookies = Cookies::new(None, None, false);
+        cookies
+            .private(&Keys::new(old_key))
+            .add(Cookie::new("session", "secret"));
+
+        let value = cookies
+            .private(&new_keys)
+            .get("session")
+            .map(|c| c.value().to_owned());
+        assert_eq!(value, Some("secret".to_owned()));
+
+        let value = cookies
+            .private(&Keys::new(new_keys.primary().clone()))
+            .get("session")
+            .map(|c| c.value().to_owned());
+        assert_eq!(value, Some("secret".to_owned()));
+    }
+
+    #[test]
+    fn add_fills_in_unset_defaults() {
+        let mut defaults = Cookie::new("", "");
+        defaults.set_path("/");
+        defaults.set_same_site(cookie::SameSite::Strict);
+
+        let mut cookies = Cookies::new(None, Some(defaults), false);
+        cookies.add(Cookie::new("foo", "1"));
+
+        let cookie = cookies.get("foo").unwrap();
+        assert_eq!(cookie.path(), Some("/"));
+        assert_eq!(cookie.same_site(), Some(cookie::SameSite::Strict));
+    }
+
+    #[test]
+    fn add_does_not_override_explicit_attributes() {
+        let mut defaults = Cookie::new("", "");
+        defaults.set_same_site(cookie::SameSite::Strict);
+
+        let mut cookies = Cookies::new(None, Some(defaults), false);
+        let mut cookie = Cookie::new("foo", "1");
+        cookie.set_same_site(cookie::SameSite::Lax);
+        cookies.add(cookie);
+
+        assert_eq!(
+            cookies.get("foo").unwrap().same_site(),
+            Some(cookie::SameSite::Lax)
+        );
+    }
+
+    #[tokio::test]
+    async fn percent_encode_escapes_delimiter_characters() {
+        let app = Router::new()
+            .route(
+                "/add",
+                get(|cookies: Extension<MutexCookies>| async move {
+                    cookies.lock().add(Cookie::new("greeting", "hello, world"));
+                }),
+            )
+            .layer(CookieManagerLayer::new().percent_encode(true))
+            .boxed();
+
+        let req = Request::builder().uri("/add").body(Body::empty()).unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        let hdr = res
+            .headers()
+            .get(header::SET_COOKIE)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(hdr.starts_with("greeting=hello%2C%20world"));
+    }
+}